@@ -1,9 +1,54 @@
+use crate::rate_limiter::RateLimiter;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use serde_json::json;
+use std::sync::Mutex;
+use tauri::ipc::Channel;
+
+const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+const DEFAULT_MODEL: &str = "gemini-2.0-flash-preview-image-generation";
+
+/// Runtime-configurable Gemini settings, replaced via `gemini_set_config`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiConfig {
+    pub auth_token: Option<String>,
+    pub auth_token_env_var_name: Option<String>,
+    pub completions_endpoint: Option<String>,
+    pub model: String,
+    #[serde(default = "default_max_requests_per_second")]
+    pub max_requests_per_second: f32,
+}
+
+fn default_max_requests_per_second() -> f32 {
+    1.0
+}
+
+impl Default for GeminiConfig {
+    fn default() -> Self {
+        Self {
+            auth_token: None,
+            auth_token_env_var_name: None,
+            completions_endpoint: None,
+            model: DEFAULT_MODEL.to_string(),
+            max_requests_per_second: default_max_requests_per_second(),
+        }
+    }
+}
+
+/// Shared, lockable `GeminiConfig` managed as Tauri state
+pub struct GeminiState(pub Mutex<GeminiConfig>);
+
+impl Default for GeminiState {
+    fn default() -> Self {
+        Self(Mutex::new(GeminiConfig::default()))
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GeminiStatusResponse {
     pub configured: bool,
+    pub model: String,
+    pub auth_source: String,
     pub message: String,
 }
 
@@ -13,6 +58,15 @@ pub struct GeminiGenerateResponse {
     pub prompt_used: String,
 }
 
+/// Incremental events emitted on a `gemini_generate_stream`/`gemini_edit_stream` channel
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "camelCase")]
+pub enum GeminiStreamEvent {
+    Progress { text: String },
+    ImageChunk { base64: String },
+    Done { prompt_used: String },
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GeminiGenerateRequest {
     pub prompt: String,
@@ -33,12 +87,51 @@ pub struct GeminiProcessRequest {
     pub style: Option<String>,
     pub custom_prompt: Option<String>,
     pub remove_background: Option<bool>,
-    pub threshold: Option<u32>,
-    pub padding: Option<u32>,
 }
 
-/// Get API key - checks compile-time env first, then runtime env
-fn get_api_key() -> Option<String> {
+/// One image or text fragment within a `GeminiTurn`, matching Gemini's own wire format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiPart {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(rename = "inlineData", skip_serializing_if = "Option::is_none")]
+    pub inline_data: Option<GeminiInlineData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiInlineData {
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    pub data: String,
+}
+
+/// One turn of a conversation, e.g. `{ role: "user", parts: [...] }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiTurn {
+    pub role: String,
+    pub parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeminiEditConversationRequest {
+    pub contents: Vec<GeminiTurn>,
+    pub system_instruction: Option<GeminiTurn>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeminiConversationResponse {
+    pub image_base64: String,
+    pub prompt_used: String,
+    pub history: Vec<GeminiTurn>,
+}
+
+/// Keeps multi-turn edits plotter-friendly when the caller doesn't supply their own instruction
+const DEFAULT_PLOTTER_SYSTEM_INSTRUCTION: &str =
+    "Always produce clean single-stroke line art suitable for a pen plotter.";
+
+/// Fall back to the bundled/compile-time key, then the runtime `GEMINI_API_KEY` env var,
+/// when no config-level token is set
+fn bundled_api_key() -> Option<String> {
     // First check if key was bundled at compile time
     const BUNDLED_KEY: Option<&str> = option_env!("GEMINI_API_KEY");
     if let Some(key) = BUNDLED_KEY {
@@ -53,79 +146,452 @@ fn get_api_key() -> Option<String> {
         .filter(|k| !k.is_empty() && k != "your_api_key_here")
 }
 
+/// Resolve the auth token to use, and a human-readable label for where it came from.
+///
+/// Order: explicit `auth_token` -> env var named by `auth_token_env_var_name` -> bundled fallback.
+fn resolve_auth_token(config: &GeminiConfig) -> Option<(String, &'static str)> {
+    if let Some(token) = &config.auth_token {
+        if !token.is_empty() {
+            return Some((token.clone(), "configured auth token"));
+        }
+    }
+
+    if let Some(var_name) = &config.auth_token_env_var_name {
+        if let Ok(token) = std::env::var(var_name) {
+            if !token.is_empty() {
+                return Some((token, "auth_token_env_var_name"));
+            }
+        }
+    }
+
+    bundled_api_key().map(|token| (token, "bundled GEMINI_API_KEY"))
+}
+
 /// Check if Gemini API is configured
 #[tauri::command]
-pub async fn gemini_check_status() -> Result<GeminiStatusResponse, String> {
-    match get_api_key() {
-        Some(_) => Ok(GeminiStatusResponse {
+pub async fn gemini_check_status(state: tauri::State<'_, GeminiState>) -> Result<GeminiStatusResponse, String> {
+    let config = state.0.lock().unwrap().clone();
+    match resolve_auth_token(&config) {
+        Some((_, source)) => Ok(GeminiStatusResponse {
             configured: true,
+            model: config.model,
+            auth_source: source.to_string(),
             message: "Gemini API is configured".to_string(),
         }),
         None => Ok(GeminiStatusResponse {
             configured: false,
+            model: config.model,
+            auth_source: "none".to_string(),
             message: "Gemini API key not configured. Configure in the app settings.".to_string(),
         }),
     }
 }
 
+/// Replace the active `GeminiConfig` with one from the app settings UI.
+/// The config is held in memory only; it is not written to disk.
+#[tauri::command]
+pub async fn gemini_set_config(
+    config: GeminiConfig,
+    state: tauri::State<'_, GeminiState>,
+) -> Result<(), String> {
+    *state.0.lock().unwrap() = config;
+    Ok(())
+}
+
 /// Generate image from text prompt
 #[tauri::command]
-pub async fn gemini_generate(request: GeminiGenerateRequest) -> Result<GeminiGenerateResponse, String> {
-    // Call Python backend script
-    call_python_backend("generate", &request)
-        .await
-        .map_err(|e| format!("Failed to generate image: {}", e))
+pub async fn gemini_generate(
+    request: GeminiGenerateRequest,
+    state: tauri::State<'_, GeminiState>,
+    limiter: tauri::State<'_, RateLimiter>,
+) -> Result<GeminiGenerateResponse, String> {
+    let config = state.0.lock().unwrap().clone();
+    let prompt = styled_prompt(&request.prompt, &request.style);
+    let body = json!({
+        "contents": [{
+            "role": "user",
+            "parts": [{ "text": prompt }],
+        }],
+        "generationConfig": generation_config(&request.width, &request.height),
+        "responseModalities": ["IMAGE"],
+    });
+
+    limiter.acquire(config.max_requests_per_second).await;
+    let response = call_gemini(&config, "generateContent", &body).await?;
+    extract_generate_response(&response, &prompt)
+}
+
+/// Fold an optional, non-empty style into the prompt as `"<prompt>, in the style of <style>"`
+fn styled_prompt(prompt: &str, style: &Option<String>) -> String {
+    match style {
+        Some(style) if !style.is_empty() => format!("{}, in the style of {}", prompt, style),
+        _ => prompt.to_string(),
+    }
 }
 
 /// Edit existing image with prompt
 #[tauri::command]
-pub async fn gemini_edit(request: GeminiEditRequest) -> Result<GeminiGenerateResponse, String> {
-    call_python_backend("edit", &request)
-        .await
-        .map_err(|e| format!("Failed to edit image: {}", e))
+pub async fn gemini_edit(
+    request: GeminiEditRequest,
+    state: tauri::State<'_, GeminiState>,
+    limiter: tauri::State<'_, RateLimiter>,
+) -> Result<GeminiGenerateResponse, String> {
+    let config = state.0.lock().unwrap().clone();
+    let body = json!({
+        "contents": [{
+            "role": "user",
+            "parts": [
+                { "text": request.prompt },
+                { "inlineData": { "mimeType": "image/png", "data": request.image_base64 } },
+            ],
+        }],
+        "responseModalities": ["IMAGE"],
+    });
+
+    limiter.acquire(config.max_requests_per_second).await;
+    let response = call_gemini(&config, "generateContent", &body).await?;
+    extract_generate_response(&response, &request.prompt)
+}
+
+/// Continue a multi-turn image edit, threading the full turn history into each call
+/// so the model keeps context across edits (e.g. "now remove the shading")
+#[tauri::command]
+pub async fn gemini_edit_conversation(
+    request: GeminiEditConversationRequest,
+    state: tauri::State<'_, GeminiState>,
+    limiter: tauri::State<'_, RateLimiter>,
+) -> Result<GeminiConversationResponse, String> {
+    let config = state.0.lock().unwrap().clone();
+
+    let system_instruction = request.system_instruction.clone().unwrap_or(GeminiTurn {
+        role: "system".to_string(),
+        parts: vec![GeminiPart {
+            text: Some(DEFAULT_PLOTTER_SYSTEM_INSTRUCTION.to_string()),
+            inline_data: None,
+        }],
+    });
+
+    let body = json!({
+        "contents": request.contents,
+        "systemInstruction": system_instruction,
+        "responseModalities": ["IMAGE"],
+    });
+
+    let last_prompt = request
+        .contents
+        .last()
+        .and_then(|turn| turn.parts.iter().find_map(|part| part.text.clone()))
+        .unwrap_or_default();
+
+    limiter.acquire(config.max_requests_per_second).await;
+    let response = call_gemini(&config, "generateContent", &body).await?;
+    let (image_base64, caption) = extract_parts(&response)?;
+    let prompt_used = if caption.is_empty() {
+        last_prompt
+    } else {
+        caption.clone()
+    };
+
+    // Carry the model's own caption into its history turn alongside the image, so the
+    // next turn's generateContent call keeps whatever context it gave about the edit.
+    let mut parts = Vec::new();
+    if !caption.is_empty() {
+        parts.push(GeminiPart {
+            text: Some(caption),
+            inline_data: None,
+        });
+    }
+    parts.push(GeminiPart {
+        text: None,
+        inline_data: Some(GeminiInlineData {
+            mime_type: "image/png".to_string(),
+            data: image_base64.clone(),
+        }),
+    });
+
+    let mut history = request.contents;
+    history.push(GeminiTurn {
+        role: "model".to_string(),
+        parts,
+    });
+
+    Ok(GeminiConversationResponse {
+        image_base64,
+        prompt_used,
+        history,
+    })
 }
 
 /// Process image for plotter
 #[tauri::command]
-pub async fn gemini_process_image(request: GeminiProcessRequest) -> Result<GeminiGenerateResponse, String> {
-    call_python_backend("process", &request)
+pub async fn gemini_process_image(
+    request: GeminiProcessRequest,
+    state: tauri::State<'_, GeminiState>,
+    limiter: tauri::State<'_, RateLimiter>,
+) -> Result<GeminiGenerateResponse, String> {
+    let config = state.0.lock().unwrap().clone();
+    let prompt = request.custom_prompt.clone().unwrap_or_else(|| {
+        format!(
+            "Convert this image into clean line art suitable for a pen plotter{}{}.",
+            request
+                .style
+                .as_ref()
+                .map(|s| format!(" in the style of {}", s))
+                .unwrap_or_default(),
+            if request.remove_background.unwrap_or(false) {
+                ", with the background removed"
+            } else {
+                ""
+            },
+        )
+    });
+
+    let body = json!({
+        "contents": [{
+            "role": "user",
+            "parts": [
+                { "text": prompt },
+                { "inlineData": { "mimeType": "image/png", "data": request.image_base64 } },
+            ],
+        }],
+        "responseModalities": ["IMAGE"],
+    });
+
+    limiter.acquire(config.max_requests_per_second).await;
+    let response = call_gemini(&config, "generateContent", &body).await?;
+    extract_generate_response(&response, &prompt)
+}
+
+/// Stream image generation progress back to the frontend as it arrives
+#[tauri::command]
+pub async fn gemini_generate_stream(
+    request: GeminiGenerateRequest,
+    state: tauri::State<'_, GeminiState>,
+    limiter: tauri::State<'_, RateLimiter>,
+    channel: Channel<GeminiStreamEvent>,
+) -> Result<(), String> {
+    let config = state.0.lock().unwrap().clone();
+    let prompt = styled_prompt(&request.prompt, &request.style);
+    let body = json!({
+        "contents": [{
+            "role": "user",
+            "parts": [{ "text": prompt }],
+        }],
+        "generationConfig": generation_config(&request.width, &request.height),
+        "responseModalities": ["IMAGE"],
+    });
+
+    limiter.acquire(config.max_requests_per_second).await;
+    call_gemini_stream(&config, &body, &channel, &prompt).await
+}
+
+/// Stream image edit progress back to the frontend as it arrives
+#[tauri::command]
+pub async fn gemini_edit_stream(
+    request: GeminiEditRequest,
+    state: tauri::State<'_, GeminiState>,
+    limiter: tauri::State<'_, RateLimiter>,
+    channel: Channel<GeminiStreamEvent>,
+) -> Result<(), String> {
+    let config = state.0.lock().unwrap().clone();
+    let body = json!({
+        "contents": [{
+            "role": "user",
+            "parts": [
+                { "text": request.prompt },
+                { "inlineData": { "mimeType": "image/png", "data": request.image_base64 } },
+            ],
+        }],
+        "responseModalities": ["IMAGE"],
+    });
+
+    limiter.acquire(config.max_requests_per_second).await;
+    call_gemini_stream(&config, &body, &channel, &request.prompt).await
+}
+
+/// Shared reqwest client so repeated Gemini calls reuse the same connection pool
+fn http_client() -> &'static reqwest::Client {
+    static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+/// Strip the `?key=...` API key out of an error message before it reaches the frontend/logs
+fn redact_key(message: String, api_key: &str) -> String {
+    message.replace(api_key, "[REDACTED]")
+}
+
+/// Build the `generationConfig` object for a text-to-image request
+fn generation_config(width: &Option<u32>, height: &Option<u32>) -> serde_json::Value {
+    let mut config = serde_json::Map::new();
+    if let (Some(width), Some(height)) = (width, height) {
+        config.insert(
+            "imageConfig".to_string(),
+            json!({ "width": width, "height": height }),
+        );
+    }
+    serde_json::Value::Object(config)
+}
+
+/// POST a `generateContent`-style request body to the Gemini REST API
+async fn call_gemini(
+    config: &GeminiConfig,
+    method: &str,
+    body: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let (api_key, _) = resolve_auth_token(config).ok_or("Gemini API key not configured")?;
+    let base = config
+        .completions_endpoint
+        .as_deref()
+        .unwrap_or(GEMINI_API_BASE);
+    let url = format!("{}/{}:{}?key={}", base, config.model, method, api_key);
+
+    let res = http_client()
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| redact_key(format!("Failed to reach Gemini API: {}", e), &api_key))?;
+
+    let json: serde_json::Value = res
+        .json()
         .await
-        .map_err(|e| format!("Failed to process image: {}", e))
-}
-
-/// Helper function to call Python backend
-async fn call_python_backend<T: Serialize, R: for<'de> Deserialize<'de>>(
-    operation: &str,
-    request: &T,
-) -> Result<R, String> {
-    // Serialize request to JSON
-    let json_input = serde_json::to_string(request)
-        .map_err(|e| format!("Failed to serialize request: {}", e))?;
-
-    // Get the path to the Python backend
-    let backend_path = std::env::current_dir()
-        .map_err(|e| format!("Failed to get current directory: {}", e))?
-        .parent()
-        .ok_or("Failed to get parent directory")?
-        .join("backend");
-
-    // Call Python script
-    let output = Command::new("python3")
-        .current_dir(&backend_path)
-        .arg("-m")
-        .arg("core.gemini.cli")
-        .arg(operation)
-        .arg(&json_input)
-        .output()
-        .map_err(|e| format!("Failed to execute Python: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Python backend error: {}", stderr));
+        .map_err(|e| format!("Failed to parse Gemini response: {}", e))?;
+
+    if let Some(error) = json.get("error") {
+        return Err(format!("Gemini API error: {}", error));
+    }
+
+    Ok(json)
+}
+
+/// POST to `streamGenerateContent` and forward each chunk to `channel` as a `GeminiStreamEvent`
+///
+/// Gemini's SSE stream is a sequence of `data: {...}\n` lines, each carrying a partial
+/// `generateContent` response; this mirrors the non-streaming parsing in
+/// `extract_generate_response` but emits each part as it arrives instead of buffering.
+async fn call_gemini_stream(
+    config: &GeminiConfig,
+    body: &serde_json::Value,
+    channel: &Channel<GeminiStreamEvent>,
+    prompt: &str,
+) -> Result<(), String> {
+    let (api_key, _) = resolve_auth_token(config).ok_or("Gemini API key not configured")?;
+    let base = config
+        .completions_endpoint
+        .as_deref()
+        .unwrap_or(GEMINI_API_BASE);
+    let url = format!(
+        "{}/{}:streamGenerateContent?alt=sse&key={}",
+        base, config.model, api_key
+    );
+
+    let res = http_client()
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| redact_key(format!("Failed to reach Gemini API: {}", e), &api_key))?;
+
+    if !res.status().is_success() {
+        let text = res.text().await.unwrap_or_default();
+        return Err(format!("Gemini API error: {}", text));
     }
 
-    // Parse response
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    serde_json::from_str(&stdout)
-        .map_err(|e| format!("Failed to parse response: {}", e))
+    let mut stream = res.bytes_stream();
+    // Raw bytes, not a String: an HTTP chunk boundary can split a multi-byte UTF-8
+    // character in half, so only decode once a full `\n`-terminated line is buffered.
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut caption = String::new();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Stream error: {}", e))?;
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+            let line_bytes: Vec<u8> = buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line_bytes).trim().to_string();
+
+            let Some(data) = line.strip_prefix("data: ") else {
+                continue;
+            };
+            if data.is_empty() {
+                continue;
+            }
+
+            let value: serde_json::Value = serde_json::from_str(data)
+                .map_err(|e| format!("Failed to parse stream chunk: {}", e))?;
+
+            if let Some(error) = value.get("error") {
+                return Err(format!("Gemini API error: {}", error));
+            }
+
+            if let Some(parts) = value["candidates"][0]["content"]["parts"].as_array() {
+                for part in parts {
+                    if let Some(data) = part["inlineData"]["data"].as_str() {
+                        channel
+                            .send(GeminiStreamEvent::ImageChunk {
+                                base64: data.to_string(),
+                            })
+                            .map_err(|e| format!("Failed to send stream event: {}", e))?;
+                    } else if let Some(text) = part["text"].as_str() {
+                        caption.push_str(text);
+                        channel
+                            .send(GeminiStreamEvent::Progress {
+                                text: text.to_string(),
+                            })
+                            .map_err(|e| format!("Failed to send stream event: {}", e))?;
+                    }
+                }
+            }
+        }
+    }
+
+    let prompt_used = if caption.is_empty() {
+        prompt.to_string()
+    } else {
+        caption
+    };
+    channel
+        .send(GeminiStreamEvent::Done { prompt_used })
+        .map_err(|e| format!("Failed to send stream event: {}", e))
+}
+
+/// Pull the image bytes and any caption text out of a `generateContent` response
+fn extract_parts(response: &serde_json::Value) -> Result<(String, String), String> {
+    let parts = response["candidates"][0]["content"]["parts"]
+        .as_array()
+        .ok_or("Gemini response did not contain any content parts")?;
+
+    let mut image_base64 = None;
+    let mut caption = String::new();
+
+    for part in parts {
+        if let Some(data) = part["inlineData"]["data"].as_str() {
+            image_base64 = Some(data.to_string());
+        } else if let Some(text) = part["text"].as_str() {
+            caption.push_str(text);
+        }
+    }
+
+    let image_base64 = image_base64.ok_or("Gemini response did not contain an image")?;
+    Ok((image_base64, caption))
+}
+
+fn extract_generate_response(
+    response: &serde_json::Value,
+    prompt: &str,
+) -> Result<GeminiGenerateResponse, String> {
+    let (image_base64, caption) = extract_parts(response)?;
+    let prompt_used = if caption.is_empty() {
+        prompt.to_string()
+    } else {
+        caption
+    };
+
+    Ok(GeminiGenerateResponse {
+        image_base64,
+        prompt_used,
+    })
 }
@@ -1,4 +1,6 @@
 mod gemini;
+mod plotter;
+mod rate_limiter;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -9,14 +11,21 @@ pub fn run() {
         .level(log::LevelFilter::Info)
         .build(),
     )
+    .manage(gemini::GeminiState::default())
+    .manage(rate_limiter::RateLimiter::default())
     .setup(|_app| {
       Ok(())
     })
     .invoke_handler(tauri::generate_handler![
       gemini::gemini_check_status,
+      gemini::gemini_set_config,
       gemini::gemini_generate,
+      gemini::gemini_generate_stream,
       gemini::gemini_edit,
+      gemini::gemini_edit_stream,
+      gemini::gemini_edit_conversation,
       gemini::gemini_process_image,
+      plotter::plotter_plot,
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
@@ -0,0 +1,147 @@
+use super::trace::Point;
+use tauri_plugin_serialplugin::desktop_api::SerialPort;
+
+pub struct PlotterParams {
+    pub pen_up_z: f64,
+    pub pen_down_z: f64,
+    pub feed_rate: f64,
+}
+
+/// Serialize ordered strokes to G-code, lifting the pen between strokes
+pub fn strokes_to_gcode(strokes: &[Vec<Point>], params: &PlotterParams) -> String {
+    let mut lines = vec![
+        "G21 ; millimeters".to_string(),
+        "G90 ; absolute positioning".to_string(),
+        format!("G1 Z{:.3} F{:.1}", params.pen_up_z, params.feed_rate),
+    ];
+
+    for stroke in strokes {
+        let Some((&(x0, y0), rest)) = stroke.split_first() else {
+            continue;
+        };
+        lines.push(format!("G0 X{:.3} Y{:.3}", x0, y0));
+        lines.push(format!("G1 Z{:.3}", params.pen_down_z));
+        for &(x, y) in rest {
+            lines.push(format!("G1 X{:.3} Y{:.3} F{:.1}", x, y, params.feed_rate));
+        }
+        lines.push(format!("G1 Z{:.3}", params.pen_up_z));
+    }
+
+    lines.push("M2 ; end of program".to_string());
+    lines.join("\n")
+}
+
+/// Serialize ordered strokes to HPGL, using PU/PD to raise and lower the pen
+pub fn strokes_to_hpgl(strokes: &[Vec<Point>]) -> String {
+    let mut lines = vec!["IN;".to_string()];
+
+    for stroke in strokes {
+        let Some((&(x0, y0), rest)) = stroke.split_first() else {
+            continue;
+        };
+        lines.push(format!("PU{},{};", x0.round(), y0.round()));
+        lines.push("PD;".to_string());
+        for &(x, y) in rest {
+            lines.push(format!("PA{},{};", x.round(), y.round()));
+        }
+    }
+
+    lines.push("PU;SP0;".to_string());
+    lines.join("\n")
+}
+
+const ACK_READ_TIMEOUT_MS: u64 = 200;
+const ACK_RETRY_LIMIT: u32 = 50;
+
+/// Stream a G-code/HPGL program to the plotter line-by-line over the port managed by
+/// `tauri_plugin_serialplugin`. `SerialPort::open`/`write`/`read` all block on the
+/// underlying `serialport` handle, so the whole exchange runs on a blocking thread
+/// rather than tying up an async worker for the length of the plot.
+pub async fn stream_program<R: tauri::Runtime>(
+    serial: SerialPort<R>,
+    port_name: String,
+    baud_rate: u32,
+    program: String,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || stream_program_blocking(&serial, &port_name, baud_rate, &program))
+        .await
+        .map_err(|e| format!("Serial streaming task panicked: {}", e))?
+}
+
+fn stream_program_blocking<R: tauri::Runtime>(
+    serial: &SerialPort<R>,
+    port_name: &str,
+    baud_rate: u32,
+    program: &str,
+) -> Result<(), String> {
+    serial
+        .open(
+            port_name.to_string(),
+            baud_rate,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .map_err(|e| format!("Failed to open serial port {}: {}", port_name, e))?;
+
+    let result = stream_lines_blocking(serial, port_name, program);
+
+    // Always release the port, even if streaming failed partway through
+    let _ = serial.close(port_name.to_string());
+    result
+}
+
+fn stream_lines_blocking<R: tauri::Runtime>(
+    serial: &SerialPort<R>,
+    port_name: &str,
+    program: &str,
+) -> Result<(), String> {
+    for line in program.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        serial
+            .write(port_name.to_string(), format!("{}\n", line))
+            .map_err(|e| format!("Failed to write `{}` to serial port: {}", line, e))?;
+
+        wait_for_ack_blocking(serial, port_name, line)?;
+    }
+
+    Ok(())
+}
+
+/// Poll the port for the controller's flow-control acknowledgement before sending the
+/// next line. Each `read` blocks for up to `ACK_READ_TIMEOUT_MS`, so this loop is a
+/// bounded number of blocking reads rather than a sleep-and-poll cycle.
+fn wait_for_ack_blocking<R: tauri::Runtime>(
+    serial: &SerialPort<R>,
+    port_name: &str,
+    line: &str,
+) -> Result<(), String> {
+    for _ in 0..ACK_RETRY_LIMIT {
+        let bytes = serial
+            .read(port_name.to_string(), Some(ACK_READ_TIMEOUT_MS), None)
+            .map_err(|e| format!("Failed to read acknowledgement for `{}`: {}", line, e))?;
+
+        if bytes.is_empty() {
+            continue;
+        }
+
+        let ack = String::from_utf8_lossy(&bytes).trim().to_ascii_lowercase();
+        if ack.contains("ok") {
+            return Ok(());
+        }
+        if ack.contains("error") {
+            return Err(format!("Plotter reported error for `{}`: {}", line, ack));
+        }
+    }
+
+    Err(format!(
+        "Timed out waiting for acknowledgement after `{}`",
+        line
+    ))
+}
@@ -0,0 +1,85 @@
+mod gcode;
+mod path;
+mod trace;
+
+use serde::{Deserialize, Serialize};
+use trace::Point;
+
+const DEFAULT_THRESHOLD: u32 = 128;
+const DEFAULT_SIMPLIFY_EPSILON: f64 = 1.0;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlotterFormat {
+    Gcode,
+    Hpgl,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlotterPlotRequest {
+    pub image_base64: String,
+    pub threshold: Option<u32>,
+    pub padding: Option<u32>,
+    pub simplify_epsilon: Option<f64>,
+    pub pen_up_z: f64,
+    pub pen_down_z: f64,
+    pub feed_rate: f64,
+    pub format: PlotterFormat,
+    pub port: String,
+    pub baud_rate: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlotterPlotResponse {
+    pub stroke_count: usize,
+    pub point_count: usize,
+}
+
+/// Trace the processed bitmap into vector strokes, order them, and stream the plot over serial
+#[tauri::command]
+pub async fn plotter_plot<R: tauri::Runtime>(
+    request: PlotterPlotRequest,
+    serial: tauri::State<'_, tauri_plugin_serialplugin::desktop_api::SerialPort<R>>,
+) -> Result<PlotterPlotResponse, String> {
+    let threshold = request.threshold.unwrap_or(DEFAULT_THRESHOLD);
+    let padding = request.padding.unwrap_or(0);
+    let epsilon = request.simplify_epsilon.unwrap_or(DEFAULT_SIMPLIFY_EPSILON);
+
+    let gray = trace::decode_image(&request.image_base64)?;
+    let bitmap = trace::threshold_bitmap(&gray, threshold, padding)?;
+
+    let strokes: Vec<Vec<Point>> = trace::trace_contours(&bitmap)
+        .into_iter()
+        .map(|chain| trace::simplify_rdp(&chain, epsilon))
+        .filter(|chain| chain.len() >= 2)
+        .collect();
+
+    let point_count = strokes.iter().map(Vec::len).sum();
+    let ordered = path::order_strokes_nearest_neighbor(strokes);
+    let stroke_count = ordered.len();
+
+    let program = match request.format {
+        PlotterFormat::Gcode => gcode::strokes_to_gcode(
+            &ordered,
+            &gcode::PlotterParams {
+                pen_up_z: request.pen_up_z,
+                pen_down_z: request.pen_down_z,
+                feed_rate: request.feed_rate,
+            },
+        ),
+        PlotterFormat::Hpgl => gcode::strokes_to_hpgl(&ordered),
+    };
+
+    gcode::stream_program(
+        serial.inner().clone(),
+        request.port.clone(),
+        request.baud_rate,
+        program,
+    )
+    .await?;
+
+    Ok(PlotterPlotResponse {
+        stroke_count,
+        point_count,
+    })
+}
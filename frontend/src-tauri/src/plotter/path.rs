@@ -0,0 +1,77 @@
+use super::trace::Point;
+
+/// Greedily chain strokes end-to-start by nearest neighbor to cut down on pen-up travel
+pub fn order_strokes_nearest_neighbor(strokes: Vec<Vec<Point>>) -> Vec<Vec<Point>> {
+    if strokes.is_empty() {
+        return strokes;
+    }
+
+    let mut remaining = strokes;
+    let first = remaining.remove(0);
+    let mut pen_position = first[first.len() - 1];
+    let mut ordered = vec![first];
+
+    while !remaining.is_empty() {
+        let mut best_index = 0;
+        let mut best_distance = f64::MAX;
+        let mut best_reversed = false;
+
+        for (i, stroke) in remaining.iter().enumerate() {
+            let to_start = distance(pen_position, stroke[0]);
+            if to_start < best_distance {
+                best_distance = to_start;
+                best_index = i;
+                best_reversed = false;
+            }
+
+            let to_end = distance(pen_position, stroke[stroke.len() - 1]);
+            if to_end < best_distance {
+                best_distance = to_end;
+                best_index = i;
+                best_reversed = true;
+            }
+        }
+
+        let mut next = remaining.remove(best_index);
+        if best_reversed {
+            next.reverse();
+        }
+        pen_position = next[next.len() - 1];
+        ordered.push(next);
+    }
+
+    ordered
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_strokes_by_nearest_endpoint() {
+        let strokes = vec![
+            vec![(10.0, 0.0), (11.0, 0.0)],
+            vec![(0.0, 0.0), (1.0, 0.0)],
+            vec![(5.0, 0.0), (6.0, 0.0)],
+        ];
+        let ordered = order_strokes_nearest_neighbor(strokes);
+        assert_eq!(
+            ordered,
+            vec![
+                vec![(10.0, 0.0), (11.0, 0.0)],
+                vec![(6.0, 0.0), (5.0, 0.0)],
+                vec![(1.0, 0.0), (0.0, 0.0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_a_single_stroke_untouched() {
+        let strokes = vec![vec![(0.0, 0.0), (1.0, 1.0)]];
+        assert_eq!(order_strokes_nearest_neighbor(strokes.clone()), strokes);
+    }
+}
@@ -0,0 +1,257 @@
+use base64::Engine;
+
+pub type Point = (f64, f64);
+
+/// Decode a base64 PNG into a grayscale bitmap for thresholding
+pub fn decode_image(image_base64: &str) -> Result<image::GrayImage, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(image_base64.trim())
+        .map_err(|e| format!("Failed to decode base64 image: {}", e))?;
+
+    image::load_from_memory(&bytes)
+        .map(|img| img.to_luma8())
+        .map_err(|e| format!("Failed to decode image: {}", e))
+}
+
+/// Bound on the padded bitmap's width/height, to keep a caller-supplied `padding` from
+/// overflowing the `u32` arithmetic below or sizing a multi-gigabyte `Vec<Vec<bool>>`.
+const MAX_PADDED_DIMENSION: u32 = 20_000;
+
+/// Threshold a grayscale image into an "ink"/"no ink" bitmap, padded on every side
+pub fn threshold_bitmap(
+    gray: &image::GrayImage,
+    threshold: u32,
+    padding: u32,
+) -> Result<Vec<Vec<bool>>, String> {
+    let (width, height) = gray.dimensions();
+
+    let pad = padding
+        .checked_mul(2)
+        .ok_or_else(|| format!("padding {} is too large", padding))?;
+    let padded_width = width
+        .checked_add(pad)
+        .ok_or_else(|| format!("padding {} is too large", padding))?;
+    let padded_height = height
+        .checked_add(pad)
+        .ok_or_else(|| format!("padding {} is too large", padding))?;
+
+    if padded_width > MAX_PADDED_DIMENSION || padded_height > MAX_PADDED_DIMENSION {
+        return Err(format!(
+            "padded image size {}x{} exceeds the {}px limit",
+            padded_width, padded_height, MAX_PADDED_DIMENSION
+        ));
+    }
+
+    let mut bitmap = vec![vec![false; padded_width as usize]; padded_height as usize];
+
+    for y in 0..height {
+        for x in 0..width {
+            if gray.get_pixel(x, y)[0] as u32 <= threshold {
+                bitmap[(y + padding) as usize][(x + padding) as usize] = true;
+            }
+        }
+    }
+
+    Ok(bitmap)
+}
+
+/// Moore-neighbor tracing: walk each connected edge of the bitmap into an ordered point chain
+pub fn trace_contours(bitmap: &[Vec<bool>]) -> Vec<Vec<Point>> {
+    let height = bitmap.len();
+    if height == 0 {
+        return Vec::new();
+    }
+    let width = bitmap[0].len();
+
+    // Clockwise 8-neighborhood starting west, matching the classic Moore-tracing walk
+    const NEIGHBORS: [(isize, isize); 8] = [
+        (-1, 0),
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+    ];
+
+    let is_ink = |x: isize, y: isize| -> bool {
+        x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && bitmap[y as usize][x as usize]
+    };
+
+    let mut visited = vec![vec![false; width]; height];
+    let mut chains = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if !bitmap[y][x] || visited[y][x] {
+                continue;
+            }
+            let is_boundary = !is_ink(x as isize - 1, y as isize)
+                || !is_ink(x as isize + 1, y as isize)
+                || !is_ink(x as isize, y as isize - 1)
+                || !is_ink(x as isize, y as isize + 1);
+            if !is_boundary {
+                continue;
+            }
+
+            let start = (x as isize, y as isize);
+            let mut current = start;
+            let mut search_from = 0usize;
+            let mut chain = Vec::new();
+
+            loop {
+                visited[current.1 as usize][current.0 as usize] = true;
+                chain.push((current.0 as f64, current.1 as f64));
+
+                let mut next = None;
+                for step in 0..8 {
+                    let dir = (search_from + step) % 8;
+                    let (dx, dy) = NEIGHBORS[dir];
+                    let candidate = (current.0 + dx, current.1 + dy);
+                    if is_ink(candidate.0, candidate.1) {
+                        // Resume the next search just behind where we arrived from
+                        next = Some((candidate, (dir + 6) % 8));
+                        break;
+                    }
+                }
+
+                match next {
+                    Some((candidate, resume_dir)) if !(candidate == start && chain.len() > 2) => {
+                        if visited[candidate.1 as usize][candidate.0 as usize] {
+                            break;
+                        }
+                        current = candidate;
+                        search_from = resume_dir;
+                    }
+                    _ => break,
+                }
+
+                // Safety valve: a bitmap can't have a simple chain longer than its pixel count
+                if chain.len() > width * height {
+                    break;
+                }
+            }
+
+            if chain.len() >= 2 {
+                chains.push(chain);
+            }
+        }
+    }
+
+    chains
+}
+
+/// Ramer-Douglas-Peucker: drop points within `epsilon` of the chord between the chain's endpoints,
+/// splitting at the farthest point and recursing on both halves when it lies beyond `epsilon`
+pub fn simplify_rdp(points: &[Point], epsilon: f64) -> Vec<Point> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let start = points[0];
+    let end = points[points.len() - 1];
+
+    let mut max_distance = 0.0;
+    let mut split_at = 0;
+    for (i, point) in points.iter().enumerate().take(points.len() - 1).skip(1) {
+        let distance = perpendicular_distance(*point, start, end);
+        if distance > max_distance {
+            max_distance = distance;
+            split_at = i;
+        }
+    }
+
+    if max_distance > epsilon {
+        let mut left = simplify_rdp(&points[..=split_at], epsilon);
+        let right = simplify_rdp(&points[split_at..], epsilon);
+        left.pop();
+        left.extend(right);
+        left
+    } else {
+        vec![start, end]
+    }
+}
+
+fn perpendicular_distance(point: Point, start: Point, end: Point) -> f64 {
+    let (x0, y0) = point;
+    let (x1, y1) = start;
+    let (x2, y2) = end;
+
+    let numerator = ((y2 - y1) * x0 - (x2 - x1) * y0 + x2 * y1 - y2 * x1).abs();
+    let denominator = ((y2 - y1).powi(2) + (x2 - x1).powi(2)).sqrt();
+
+    if denominator == 0.0 {
+        ((x0 - x1).powi(2) + (y0 - y1).powi(2)).sqrt()
+    } else {
+        numerator / denominator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rdp_collapses_a_straight_line_to_its_endpoints() {
+        let points = vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0), (4.0, 0.0)];
+        let simplified = simplify_rdp(&points, 0.5);
+        assert_eq!(simplified, vec![(0.0, 0.0), (4.0, 0.0)]);
+    }
+
+    #[test]
+    fn rdp_keeps_a_corner_that_exceeds_epsilon() {
+        // An L-shaped chain: the corner at (0, 4) sits about 2.83 units off the (0,0)-(4,4) chord.
+        let points = vec![(0.0, 0.0), (0.0, 4.0), (4.0, 4.0)];
+        let simplified = simplify_rdp(&points, 1.0);
+        assert_eq!(simplified, points);
+    }
+
+    #[test]
+    fn rdp_drops_points_within_epsilon_of_the_chord() {
+        let points = vec![(0.0, 0.0), (2.0, 0.1), (4.0, 0.0)];
+        let simplified = simplify_rdp(&points, 0.5);
+        assert_eq!(simplified, vec![(0.0, 0.0), (4.0, 0.0)]);
+    }
+
+    #[test]
+    fn rdp_leaves_short_chains_untouched() {
+        let points = vec![(0.0, 0.0), (1.0, 1.0)];
+        assert_eq!(simplify_rdp(&points, 0.1), points);
+    }
+
+    #[test]
+    fn trace_contours_on_empty_bitmap_is_empty() {
+        assert!(trace_contours(&[]).is_empty());
+    }
+
+    #[test]
+    fn trace_contours_ignores_an_isolated_single_pixel() {
+        // A lone ink pixel has no ink neighbor to walk to, so it can't form a chain.
+        let bitmap = vec![
+            vec![false, false, false],
+            vec![false, true, false],
+            vec![false, false, false],
+        ];
+        assert!(trace_contours(&bitmap).is_empty());
+    }
+
+    #[test]
+    fn trace_contours_walks_a_solid_block_boundary() {
+        let bitmap = vec![
+            vec![false, false, false, false],
+            vec![false, true, true, false],
+            vec![false, true, true, false],
+            vec![false, false, false, false],
+        ];
+        let chains = trace_contours(&bitmap);
+
+        assert!(!chains.is_empty());
+        for chain in &chains {
+            assert!(chain.len() >= 2, "a traced chain must have at least 2 points");
+            for &(x, y) in chain {
+                assert!(bitmap[y as usize][x as usize], "traced point must be an ink pixel");
+            }
+        }
+    }
+}
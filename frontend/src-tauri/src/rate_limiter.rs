@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Token-bucket limiter shared across all Gemini commands so bursts of
+/// generate/edit/process calls don't blow through the API's per-second cap.
+pub struct RateLimiter(Mutex<RateLimiterState>);
+
+struct RateLimiterState {
+    tokens: f32,
+    last_refill: Instant,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self(Mutex::new(RateLimiterState {
+            tokens: 1.0,
+            last_refill: Instant::now(),
+        }))
+    }
+}
+
+impl RateLimiter {
+    /// Block until a token is available for a request at `max_requests_per_second`.
+    ///
+    /// The bucket refills by `max_requests_per_second` tokens per elapsed second,
+    /// capped at a bucket size of `max_requests_per_second` (but never less than one
+    /// token), so a burst can only ever spend what it
+    /// has accumulated while idle.
+    pub async fn acquire(&self, max_requests_per_second: f32) {
+        loop {
+            let wait = {
+                let mut state = self.0.lock().await;
+                let now = Instant::now();
+                let elapsed = now.duration_since(state.last_refill).as_secs_f32();
+                state.last_refill = now;
+                state.tokens = (state.tokens + elapsed * max_requests_per_second)
+                    .min(max_requests_per_second.max(1.0));
+
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    None
+                } else {
+                    let deficit = 1.0 - state.tokens;
+                    Some(Duration::from_secs_f32(
+                        deficit / max_requests_per_second.max(0.001),
+                    ))
+                }
+            };
+
+            match wait {
+                None => return,
+                Some(duration) => tokio::time::sleep(duration).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn acquiring_n_requests_hits_the_expected_wall_clock_floor() {
+        let limiter = RateLimiter::default();
+        let rate = 20.0; // one token every 50ms once the initial bucket is spent
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire(rate).await;
+        }
+        let elapsed = start.elapsed();
+
+        // The bucket starts full (1 token), so only the 2nd and 3rd acquires wait.
+        assert!(
+            elapsed >= Duration::from_millis(90),
+            "3 acquires at {} req/s returned in {:?}, faster than the refill floor",
+            rate,
+            elapsed
+        );
+    }
+
+    #[tokio::test]
+    async fn zero_rate_still_waits_instead_of_dividing_by_zero() {
+        let limiter = RateLimiter::default();
+
+        // First acquire is free from the initial bucket.
+        limiter.acquire(0.0).await;
+
+        // The second must wait on the `max(0.001)` floor rather than panicking or
+        // returning immediately, so a short timeout should elapse without completing.
+        let result = tokio::time::timeout(Duration::from_millis(50), limiter.acquire(0.0)).await;
+        assert!(
+            result.is_err(),
+            "acquire() at a zero rate should block the caller, not return instantly"
+        );
+    }
+}